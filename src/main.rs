@@ -1,47 +1,293 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use serde_derive::{Deserialize, Serialize};
-use std::{error::Error, fs, io};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans, Text},
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs},
     Frame, Terminal,
 };
 use unicode_width::UnicodeWidthStr;
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+enum InputMode {
+    Normal,
+    Editing,
+}
+
+/// Which subset of the current list's todos are shown.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+enum Filter {
+    All,
+    Pending,
+    Done,
+}
+
+impl Filter {
+    fn next(self) -> Filter {
+        match self {
+            Filter::All => Filter::Pending,
+            Filter::Pending => Filter::Done,
+            Filter::Done => Filter::All,
+        }
+    }
+    fn label(self) -> &'static str {
+        match self {
+            Filter::All => "All",
+            Filter::Pending => "Pending",
+            Filter::Done => "Done",
+        }
+    }
+}
+
+/// The current on-disk schema version, bumped whenever `Todo` or `TodoFile` gain fields.
+const TODO_FILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Todo {
+    text: String,
+    created_at: DateTime<Local>,
+    done: bool,
+    priority: u8,
+}
+
+impl Todo {
+    fn new(text: String) -> Todo {
+        Todo {
+            text,
+            created_at: Local::now(),
+            done: false,
+            priority: 0,
+        }
+    }
+    /// Builds a `Todo` from a pre-v1 save, recovering `created_at` from the
+    /// trailing `[<timestamp>]` suffix the old flat format jammed into the text.
+    fn from_legacy_text(raw: String) -> Todo {
+        let mut todo = Todo::new(raw.clone());
+        if let Some(open) = raw.rfind(" [") {
+            if raw.ends_with(']') {
+                let stamped = format!("{} {}", &raw[open + 2..raw.len() - 1], Local::now().year());
+                if let Ok(naive) = NaiveDateTime::parse_from_str(&stamped, "%B %d %I:%M %p %Y") {
+                    if let Some(created_at) = Local.from_local_datetime(&naive).single() {
+                        todo.text = raw[..open].to_string();
+                        todo.created_at = created_at;
+                    }
+                }
+            }
+        }
+        todo
+    }
+}
+
+/// A pre-v1 todo, back when the only extra piece of state was `done`.
+#[derive(Deserialize)]
+struct LegacyTodo {
+    text: String,
+    done: bool,
+}
+
+impl From<LegacyTodo> for Todo {
+    fn from(legacy: LegacyTodo) -> Todo {
+        let mut todo = Todo::from_legacy_text(legacy.text);
+        todo.done = legacy.done;
+        todo
+    }
+}
+
+/// Top-level on-disk format, versioned so the schema can evolve without breaking old saves.
+#[derive(Serialize, Deserialize, Clone)]
+struct TodoFile {
+    version: u32,
+    lists: BTreeMap<String, Vec<Todo>>,
+}
+
+/// Reads a save file, migrating it forward from any older schema this crate has shipped.
+/// The result always has at least one list: `App::current_list`/`current_list_mut` assume
+/// `tabs.titles` is never empty, so a blank or hand-edited `{}` save can't produce one.
+fn load_todo_file(contents: &str) -> serde_json::Result<BTreeMap<String, Vec<Todo>>> {
+    let mut lists = load_todo_lists(contents)?;
+    if lists.is_empty() {
+        lists.insert("Todos".to_string(), Vec::new());
+    }
+    Ok(lists)
+}
+
+fn load_todo_lists(contents: &str) -> serde_json::Result<BTreeMap<String, Vec<Todo>>> {
+    if let Ok(file) = serde_json::from_str::<TodoFile>(contents) {
+        return Ok(file.lists);
+    }
+    if let Ok(lists) = serde_json::from_str::<BTreeMap<String, Vec<LegacyTodo>>>(contents) {
+        return Ok(lists
+            .into_iter()
+            .map(|(name, todos)| (name, todos.into_iter().map(Todo::from).collect()))
+            .collect());
+    }
+    if let Ok(lists) = serde_json::from_str::<BTreeMap<String, Vec<String>>>(contents) {
+        return Ok(lists
+            .into_iter()
+            .map(|(name, todos)| {
+                (
+                    name,
+                    todos.into_iter().map(Todo::from_legacy_text).collect(),
+                )
+            })
+            .collect());
+    }
+    let flat: Vec<String> = serde_json::from_str(contents)?;
+    let mut lists = BTreeMap::new();
+    lists.insert(
+        "Todos".to_string(),
+        flat.into_iter().map(Todo::from_legacy_text).collect(),
+    );
+    Ok(lists)
+}
+
+/// Resolves where the save file lives: `TUIDO_CONFIG` if set, otherwise the
+/// platform's config directory (`$XDG_CONFIG_HOME`, `~/Library/Application Support`,
+/// `%APPDATA%`, ...), creating it if it doesn't exist yet.
+fn config_path() -> io::Result<PathBuf> {
+    if let Ok(path) = std::env::var("TUIDO_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let mut dir = dirs::config_dir().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "could not determine the platform config directory",
+        )
+    })?;
+    dir.push("tuido");
+    fs::create_dir_all(&dir)?;
+    dir.push("todos.json");
+    Ok(dir)
+}
+
+/// Writes the save file atomically: a crash or power loss mid-write leaves the
+/// previous file intact instead of a half-written, corrupt one.
+fn save_todo_file(path: &Path, file: &TodoFile) -> io::Result<()> {
+    let json = serde_json::to_vec(file)?;
+    let tmp_path = path.with_extension("json.tmp");
+    let mut tmp = fs::File::create(&tmp_path)?;
+    io::Write::write_all(&mut tmp, &json)?;
+    tmp.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Tracks which list tab is selected, modeled on the tui-rs `Tabs` demo.
+#[derive(Serialize, Deserialize, Clone)]
+struct TabsState {
+    titles: Vec<String>,
+    index: usize,
+}
+
+impl TabsState {
+    fn new(titles: Vec<String>) -> TabsState {
+        TabsState { titles, index: 0 }
+    }
+    fn next(&mut self) {
+        self.index = (self.index + 1) % self.titles.len();
+    }
+    fn previous(&mut self) {
+        if self.index > 0 {
+            self.index -= 1;
+        } else {
+            self.index = self.titles.len() - 1;
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 struct App {
     input: String,
-    todos: Vec<String>,
+    input_mode: InputMode,
+    filter: Filter,
+    lists: BTreeMap<String, Vec<Todo>>,
+    tabs: TabsState,
     index: usize,
     show_popup: bool,
+    load_error: Option<String>,
 }
 
 impl App {
     fn default() -> App {
+        let mut lists = BTreeMap::new();
+        lists.insert("Work".to_string(), Vec::new());
+        lists.insert("Home".to_string(), Vec::new());
+        let tabs = TabsState::new(lists.keys().cloned().collect());
         App {
             input: String::new(),
-            todos: Vec::new(),
+            input_mode: InputMode::Normal,
+            filter: Filter::All,
+            lists,
+            tabs,
             index: 0,
             show_popup: false,
+            load_error: None,
         }
     }
+    fn current_list(&self) -> &Vec<Todo> {
+        self.lists
+            .get(&self.tabs.titles[self.tabs.index])
+            .expect("selected tab always has a matching list")
+    }
+    fn current_list_mut(&mut self) -> &mut Vec<Todo> {
+        self.lists
+            .get_mut(&self.tabs.titles[self.tabs.index])
+            .expect("selected tab always has a matching list")
+    }
+    /// Indices into the current list of the todos visible under the active filter.
+    fn visible(&self) -> Vec<usize> {
+        self.current_list()
+            .iter()
+            .enumerate()
+            .filter(|(_, todo)| match self.filter {
+                Filter::All => true,
+                Filter::Pending => !todo.done,
+                Filter::Done => todo.done,
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
     fn next(&mut self) {
-        self.index = (self.index + 1) % self.todos.len();
+        self.index = (self.index + 1) % self.visible().len();
     }
     fn previous(&mut self) {
         if self.index > 0 {
             self.index -= 1;
         } else {
-            self.index = self.todos.len() - 1;
+            self.index = self.visible().len() - 1;
         }
     }
+    fn next_tab(&mut self) {
+        self.tabs.next();
+        self.index = 0;
+    }
+    fn previous_tab(&mut self) {
+        self.tabs.previous();
+        self.index = 0;
+    }
+    fn sort_by_date(&mut self) {
+        self.current_list_mut().sort_by_key(|todo| todo.created_at);
+        self.index = 0;
+    }
+    fn sort_by_priority(&mut self) {
+        self.current_list_mut()
+            .sort_by_key(|todo| std::cmp::Reverse(todo.priority));
+        self.index = 0;
+    }
     fn chain_hook(&mut self) {
         let original_hook = std::panic::take_hook();
 
@@ -90,65 +336,120 @@ fn reset_terminal() -> io::Result<()> {
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
     app.chain_hook();
 
-    let todo_path: &String = &format!("/home/{}/.config/todos.json", env!("USER"));
+    let todo_path = config_path()?;
 
-    if let Ok(todos_json) = fs::read_to_string(todo_path) {
-        let todos: Vec<String> = serde_json::from_str(&todos_json)?;
-        app.todos = todos;
-    } else {
-        let todos_json = serde_json::to_vec(&app.todos)?;
-        fs::write(todo_path, todos_json)?;
+    match fs::read_to_string(&todo_path) {
+        Ok(todos_json) => match load_todo_file(&todos_json) {
+            Ok(lists) => {
+                app.tabs = TabsState::new(lists.keys().cloned().collect());
+                app.lists = lists;
+            }
+            Err(err) => {
+                app.load_error = Some(format!("couldn't parse {}: {}", todo_path.display(), err));
+            }
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let file = TodoFile {
+                version: TODO_FILE_VERSION,
+                lists: app.lists.clone(),
+            };
+            save_todo_file(&todo_path, &file)?;
+        }
+        Err(err) => {
+            app.load_error = Some(format!("couldn't read {}: {}", todo_path.display(), err));
+        }
     }
+    // Kept separately from load_error, which Esc clears once the popup is dismissed.
+    let load_failed = app.load_error.is_some();
 
     loop {
         terminal.draw(|f| ui(f, &app))?;
 
-        let time = chrono::Local::now().format("%B %d %I:%M %p");
-
         if let Event::Key(key) = event::read()? {
-            match key.code {
-                KeyCode::Enter => {
-                    if !app.input.is_empty() {
-                        app.todos.push(format!(
-                            "{} [{}]",
-                            app.input.drain(..).collect::<String>(),
-                            time
-                        ));
-                    } else {
-                        app.show_popup = !app.show_popup
+            match app.input_mode {
+                InputMode::Normal => match key.code {
+                    KeyCode::Char('i') | KeyCode::Char('a') | KeyCode::Enter => {
+                        app.input_mode = InputMode::Editing;
                     }
-                }
-                KeyCode::Up => {
-                    if !app.todos.is_empty() {
+                    KeyCode::Char('q') => {
+                        // Never overwrite a save we couldn't parse: app.lists is just
+                        // App::default()'s blank lists, not the real (unreadable) data.
+                        if !load_failed {
+                            let file = TodoFile {
+                                version: TODO_FILE_VERSION,
+                                lists: app.lists.clone(),
+                            };
+                            save_todo_file(&todo_path, &file)?;
+                        }
+                        return Ok(());
+                    }
+                    KeyCode::Char('p') => app.show_popup = !app.show_popup,
+                    KeyCode::Char('f') => {
+                        app.filter = app.filter.next();
+                        app.index = 0;
+                    }
+                    KeyCode::Char('s') => app.sort_by_date(),
+                    KeyCode::Char('S') => app.sort_by_priority(),
+                    KeyCode::Char('+') => {
+                        if let Some(&real_index) = app.visible().get(app.index) {
+                            let priority = app.current_list()[real_index].priority;
+                            app.current_list_mut()[real_index].priority =
+                                priority.saturating_add(1).min(9);
+                        }
+                    }
+                    KeyCode::Char('-') => {
+                        if let Some(&real_index) = app.visible().get(app.index) {
+                            let priority = app.current_list()[real_index].priority;
+                            app.current_list_mut()[real_index].priority =
+                                priority.saturating_sub(1);
+                        }
+                    }
+                    KeyCode::Char('k') | KeyCode::Up if !app.visible().is_empty() => {
                         app.previous();
                     }
-                }
-                KeyCode::Down => {
-                    if !app.todos.is_empty() {
+                    KeyCode::Char('j') | KeyCode::Down if !app.visible().is_empty() => {
                         app.next();
                     }
-                }
-                KeyCode::Char(c) => {
-                    app.input.push(c);
-                }
-                KeyCode::Backspace => {
-                    app.input.pop();
-                }
-                KeyCode::Tab => {
-                    if !app.todos.is_empty() {
-                        if app.index < app.todos.len() {
-                            app.todos.remove(app.index);
-                        } else {
-                            app.index = app.todos.len() - 1;
+                    KeyCode::Char('h') | KeyCode::Left => app.previous_tab(),
+                    KeyCode::Char('l') | KeyCode::Right => app.next_tab(),
+                    KeyCode::Char(' ') => {
+                        if let Some(&real_index) = app.visible().get(app.index) {
+                            let done = app.current_list()[real_index].done;
+                            app.current_list_mut()[real_index].done = !done;
                         }
                     }
-                }
-                KeyCode::Esc => {
-                    let json = serde_json::to_vec(&app.todos)?;
-                    fs::write(todo_path, json)?;
-                    return Ok(());
-                }
-                _ => {}
+                    KeyCode::Char('d') | KeyCode::Tab => {
+                        let visible = app.visible();
+                        if let Some(&real_index) = visible.get(app.index) {
+                            app.current_list_mut().remove(real_index);
+                            let len = app.visible().len();
+                            if app.index >= len && len > 0 {
+                                app.index = len - 1;
+                            }
+                        }
+                    }
+                    KeyCode::Esc => app.load_error = None,
+                    _ => {}
+                },
+                InputMode::Editing => match key.code {
+                    KeyCode::Enter => {
+                        if !app.input.is_empty() {
+                            let text = app.input.drain(..).collect();
+                            app.current_list_mut().push(Todo::new(text));
+                        }
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Char(c) => {
+                        app.input.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        app.input.pop();
+                    }
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                    }
+                    _ => {}
+                },
             }
         }
     }
@@ -159,6 +460,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
             .margin(2)
             .constraints(
                 [
+                    Constraint::Length(3),
                     Constraint::Length(1),
                     Constraint::Length(3),
                     Constraint::Min(1),
@@ -167,49 +469,145 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
             )
             .split(f.size());
 
-        let (msg, style) = (
-            vec![
-                Span::raw("Press "),
-                Span::styled(
-                    "Up/Down key",
-                    Style::default()
-                        .add_modifier(Modifier::BOLD)
-                        .fg(Color::Green),
-                ),
-                Span::raw(" to navigate, "),
-                Span::styled(
-                    "Tab",
-                    Style::default()
-                        .add_modifier(Modifier::BOLD)
-                        .fg(Color::Green),
-                ),
-                Span::raw(" to remove TODO, "),
-                Span::styled(
-                    "Esc",
-                    Style::default()
-                        .add_modifier(Modifier::BOLD)
-                        .fg(Color::Green),
-                ),
-                Span::raw(" to exit. "),
-            ],
-            Style::default().add_modifier(Modifier::BOLD),
-        );
+        let titles: Vec<Spans> = app
+            .tabs
+            .titles
+            .iter()
+            .map(|t| Spans::from(Span::styled(t.as_str(), Style::default().fg(Color::Green))))
+            .collect();
+        let tabs = Tabs::new(titles)
+            .block(Block::default().borders(Borders::ALL).title("Lists"))
+            .select(app.tabs.index)
+            .style(Style::default().fg(Color::White))
+            .highlight_style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .fg(Color::Yellow),
+            );
+        f.render_widget(tabs, chunks[0]);
+
+        let (msg, style) = match app.input_mode {
+            InputMode::Normal => (
+                vec![
+                    Span::raw("Press "),
+                    Span::styled(
+                        "i/a",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to add, "),
+                    Span::styled(
+                        "j/k",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to navigate, "),
+                    Span::styled(
+                        "h/l",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to switch lists, "),
+                    Span::styled(
+                        "d",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to remove, "),
+                    Span::styled(
+                        "Space",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to toggle done, "),
+                    Span::styled(
+                        "f",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to filter, "),
+                    Span::styled(
+                        "s/S",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to sort by date/priority, "),
+                    Span::styled(
+                        "p",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" for info, "),
+                    Span::styled(
+                        "q",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to exit. "),
+                ],
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            InputMode::Editing => (
+                vec![
+                    Span::raw("Press "),
+                    Span::styled(
+                        "Esc",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to stop editing, "),
+                    Span::styled(
+                        "Enter",
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(Color::Green),
+                    ),
+                    Span::raw(" to add the TODO. "),
+                ],
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        };
         let mut text = Text::from(Spans::from(msg));
         text.patch_style(style);
         let help_message = Paragraph::new(text);
-        f.render_widget(help_message, chunks[0]);
+        f.render_widget(help_message, chunks[1]);
 
         let input = Paragraph::new(app.input.as_ref())
             .block(Block::default().borders(Borders::ALL).title("Add a TODO"));
-        f.render_widget(input, chunks[1]);
-        f.set_cursor(chunks[1].x + app.input.width() as u16 + 1, chunks[1].y + 1);
+        f.render_widget(input, chunks[2]);
+        if app.input_mode == InputMode::Editing {
+            f.set_cursor(chunks[2].x + app.input.width() as u16 + 1, chunks[2].y + 1);
+        }
+
+        let current_list = app.current_list();
+        let visible = app.visible();
 
-        let todos: Vec<ListItem> = app
-            .todos
+        let todos: Vec<ListItem> = visible
             .iter()
             .enumerate()
-            .map(|(i, m)| {
-                let content = vec![Spans::from(Span::raw(format!("{}: {}", i + 1, m)))];
+            .map(|(display_i, &real_i)| {
+                let todo = &current_list[real_i];
+                let style = if todo.done {
+                    Style::default()
+                        .add_modifier(Modifier::CROSSED_OUT)
+                        .add_modifier(Modifier::DIM)
+                } else {
+                    Style::default()
+                };
+                let content = vec![Spans::from(Span::styled(
+                    format!("{}: {}", display_i + 1, todo.text),
+                    style,
+                ))];
                 ListItem::new(content)
             })
             .collect();
@@ -218,21 +616,45 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
         state.select(Some(app.index));
 
         let todos = List::new(todos)
-            .block(Block::default().borders(Borders::ALL).title("Todo(s)"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Todo(s) [{}]", app.filter.label())),
+            )
             .style(Style::default().fg(Color::White))
             .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
             .highlight_symbol("> ");
-        f.render_stateful_widget(todos, chunks[2], &mut state);
+        f.render_stateful_widget(todos, chunks[3], &mut state);
 
-        if app.index < app.todos.len() {
+        if let Some(&real_i) = visible.get(app.index) {
             if app.show_popup {
-                let block = Paragraph::new(format!("{}", app.todos[app.index]))
-                    .block(Block::default().borders(Borders::ALL).title("More info"));
+                let todo = &current_list[real_i];
+                let block = Paragraph::new(format!(
+                    "Text: {}\nCreated: {}\nPriority: {}\nDone: {}",
+                    todo.text,
+                    todo.created_at.format("%B %d, %Y %I:%M %p"),
+                    todo.priority,
+                    todo.done
+                ))
+                .block(Block::default().borders(Borders::ALL).title("More info"));
                 let area = centered_rect(60, 20, f.size());
                 f.render_widget(Clear, area); //this clears out the background
                 f.render_widget(block, area);
             }
         }
+
+        if let Some(err) = &app.load_error {
+            let block = Paragraph::new(err.as_str())
+                .style(Style::default().fg(Color::Red))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Load error (Esc to dismiss)"),
+                );
+            let area = centered_rect(60, 20, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(block, area);
+        }
     }
 }
 